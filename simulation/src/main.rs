@@ -1,7 +1,6 @@
 #![allow(non_snake_case)]
 
 use ::chrono::prelude::*;
-use ::ndarray::prelude::*;
 use ::pbr::ProgressBar;
 use ::rand::prelude::*;
 use ::rayon::prelude::*;
@@ -16,37 +15,47 @@ use std::{
 };
 
 use ising_lib::prelude::*;
+use ising_lib::simulation::equilibrate;
 
 const SIZE: usize = 50;
 const T_MIN: f64 = 0.1;
 const T_MAX: f64 = 5.0;
 const T_STEP: f64 = 0.1;
+const K: f32 = 1.0;
 const FLIPS_TO_SKIP: usize = 60_000;
+const EQUILIBRATION_BLOCK_SIZE: usize = 1_000;
 const MEASUREMENTS_PER_T: usize = 1000;
-const ATTEMPTS_PER_FLIP: usize = 20;
+const FLIPS_PER_MEASUREMENT: usize = SIZE * SIZE;
+const EQUILIBRATION_TOLERANCE: f64 = 1e-3;
 
 struct Params {
     T_range: (f64, f64),
     flips_to_skip: usize,
     measurements_per_T: usize,
     flips_per_measurement: usize,
-    attempts_per_flip: usize,
     lattice_size: usize,
-    J: f64,
-    h: f64,
+    J: f32,
+    seed: u64,
+    /// Directory whose `frames` subdirectory frames are exported to when
+    /// `export_frames` is set.
+    output_dir: String,
+    /// Dump a PGM frame of the lattice at every temperature, so a movie of
+    /// domain formation across the phase transition can be assembled.
+    export_frames: bool,
 }
 
 impl Params {
-    fn new(J: f64, h: f64) -> Self {
+    fn new(J: f32, seed: u64, output_dir: String, export_frames: bool) -> Self {
         Self {
             T_range: (T_MIN, T_MAX),
             flips_to_skip: FLIPS_TO_SKIP,
             measurements_per_T: MEASUREMENTS_PER_T,
-            flips_per_measurement: SIZE * SIZE,
-            attempts_per_flip: ATTEMPTS_PER_FLIP,
+            flips_per_measurement: FLIPS_PER_MEASUREMENT,
             lattice_size: SIZE,
             J,
-            h,
+            seed,
+            output_dir,
+            export_frames,
         }
     }
 }
@@ -58,8 +67,8 @@ struct Record {
     X: f64,
 }
 
-fn compose_results(records: &[Record], params: Params) -> String {
-    let records = records
+fn compose_results(records: &[Record], params: Params, Tc: Option<f64>) -> String {
+    let records_json = records
         .iter()
         .map(|r| {
             json!({
@@ -72,15 +81,48 @@ fn compose_results(records: &[Record], params: Params) -> String {
         .collect::<Vec<_>>();
 
     to_string_pretty(&json!({
-        "records": records,
+        "records": records_json,
+        "Tc": Tc,
         "params": {
             "J": params.J,
-            "h": params.h,
+            "seed": params.seed,
         },
     }))
     .unwrap()
 }
 
+/// Locates the critical temperature as the `T` of the peak susceptibility,
+/// sharpened beyond the raw grid spacing by fitting a parabola through the
+/// peak sample and its two (already `T`-sorted) neighbors and returning the
+/// fitted vertex instead of the grid sample itself.
+fn locate_critical_temperature(records: &[Record]) -> Option<f64> {
+    let (peak_i, _) = records.iter().enumerate().max_by(|(_, a), (_, b)| {
+        a.X.partial_cmp(&b.X).unwrap_or(std::cmp::Ordering::Less)
+    })?;
+
+    if peak_i == 0 || peak_i == records.len() - 1 {
+        return Some(records[peak_i].T);
+    }
+
+    let (x0, x1, x2) = (
+        records[peak_i - 1].X,
+        records[peak_i].X,
+        records[peak_i + 1].X,
+    );
+    let denom = x0 - 2.0 * x1 + x2;
+
+    if denom.abs() < f64::EPSILON {
+        return Some(records[peak_i].T);
+    }
+
+    // Vertex offset (in grid-step units) of a parabola through the three
+    // points, assuming the grid step is uniform around the peak.
+    let offset = 0.5 * (x0 - x2) / denom;
+    let T_step = records[peak_i + 1].T - records[peak_i].T;
+
+    Some(records[peak_i].T + offset * T_step)
+}
+
 fn compose_file_name() -> String {
     let now = Local::now().format("%d.%m.%Y-%H.%M").to_string();
     let id = thread_rng().gen_range(100_i32, 999_i32);
@@ -92,31 +134,48 @@ fn cmp_by_T(a: &Record, b: &Record) -> std::cmp::Ordering {
     a.T.partial_cmp(&b.T).unwrap_or(std::cmp::Ordering::Less)
 }
 
-fn run(params: Params, pb_tx: Sender<()>) -> (String, String) {
-    let mut rng = SmallRng::from_entropy();
-    let mut lattice = Lattice::new((params.lattice_size, params.lattice_size));
-    let Ts: Vec<f64> = TRange::from_step(params.T_range.0, params.T_range.1, T_STEP).collect();
-    let h = Array::from_elem((params.lattice_size, params.lattice_size), params.h);
-
-    // "cool" the lattice to its natural state
-    (0..params.flips_to_skip).for_each(|_| {
-        let _ = (0..params.attempts_per_flip)
-            .map(|_| {
-                let ix = lattice.gen_random_index();
-                let E_diff = lattice.measure_E_diff(ix, params.J);
-                let probability = calc_flip_probability(E_diff, params.T_range.0);
-
-                if probability > rng.gen() {
-                    lattice.flip_spin(ix);
-
-                    true
-                } else {
-                    false
-                }
-            })
-            .take_while(|already_flipped| !already_flipped)
-            .count();
-    });
+fn gen_random_index(size: usize, rng: &mut impl Rng) -> (usize, usize) {
+    (rng.gen_range(0, size), rng.gen_range(0, size))
+}
+
+/// Writes the lattice's spin grid as a binary PGM frame (`-1` -> `0`, `+1`
+/// -> `255`), so a sequence of frames across a temperature sweep can be
+/// assembled into a movie of domain formation.
+fn write_pgm_frame(lattice: &Lattice, path: impl AsRef<Path>) -> std::io::Result<()> {
+    let size = lattice.size();
+    let mut bytes = format!("P5\n{} {}\n255\n", size, size).into_bytes();
+
+    bytes.extend(
+        lattice
+            .as_array()
+            .iter()
+            .map(|&spin| if spin == 1 { 255_u8 } else { 0_u8 }),
+    );
+
+    fs::write(path, bytes)
+}
+
+/// Runs the measurement sweep with a caller-chosen seedable RNG `R`, so a
+/// result file's `seed` can be fed back in to replay the exact same run.
+fn run<R: Rng + SeedableRng>(params: Params, pb_tx: Sender<()>) -> (String, String) {
+    let mut rng = R::seed_from_u64(params.seed);
+    let mut lattice = Lattice::new_seeded(params.lattice_size, params.J, &mut rng);
+    let Ts: Vec<f64> =
+        TRange::new_step(params.T_range.0, params.T_range.1, T_STEP).collect();
+
+    // "Cool" the lattice to its natural state, stopping as soon as the
+    // block-mean energy stops moving instead of always spending the full
+    // `flips_to_skip` budget.
+    equilibrate(
+        &mut lattice,
+        params.J,
+        K,
+        params.T_range.0,
+        EQUILIBRATION_BLOCK_SIZE,
+        params.flips_to_skip,
+        EQUILIBRATION_TOLERANCE,
+        &mut rng,
+    );
 
     let mut records: Vec<Record> = Ts
         .into_iter()
@@ -124,33 +183,32 @@ fn run(params: Params, pb_tx: Sender<()>) -> (String, String) {
             let (Es, Is) = (0..params.measurements_per_T)
                 .map(|_| {
                     (0..params.flips_per_measurement).for_each(|_| {
-                        let _ = (0..params.attempts_per_flip)
-                            .map(|_| {
-                                let ix = lattice.gen_random_index();
-                                let E_diff = lattice.measure_E_diff_with_h(ix, &h, params.J);
-                                let probability = calc_flip_probability(E_diff, T);
-
-                                if probability > rng.gen() {
-                                    lattice.flip_spin(ix);
-
-                                    true // the flip has already occured
-                                } else {
-                                    false // the flip has not occured yet
-                                }
-                            })
-                            .take_while(|already_flipped| !already_flipped)
-                            .count();
+                        let ix = gen_random_index(lattice.size(), &mut rng);
+                        let E_diff = f64::from(lattice.calc_dE(ix, params.J));
+                        let probability = calc_flip_probability(E_diff, T, f64::from(K));
+
+                        if probability > rng.gen() {
+                            lattice.flip_spin(ix, params.J);
+                        }
                     });
 
                     let _ = pb_tx.send(());
 
-                    (lattice.measure_E(params.J), lattice.measure_I())
+                    (f64::from(lattice.current_E()), f64::from(lattice.current_I()))
                 })
                 .unzip::<_, _, Vec<_>, Vec<_>>();
 
+            if params.export_frames {
+                let frames_dir = format!("{}/frames", params.output_dir);
+                let frame_path = format!("{}/J{}-T{:.2}.pgm", frames_dir, params.J, T);
+
+                let _ = fs::create_dir_all(&frames_dir);
+                let _ = write_pgm_frame(&lattice, &frame_path);
+            }
+
             let dE = calc_dE(&Es, T);
-            let I = calc_I(&Is);
-            let X = calc_X(&Es);
+            let I = Is.iter().sum::<f64>() / Is.len() as f64;
+            let X = calc_X(&Is);
 
             Record { T, dE, I, X }
         })
@@ -158,7 +216,8 @@ fn run(params: Params, pb_tx: Sender<()>) -> (String, String) {
 
     let file_name = compose_file_name();
     records.sort_by(cmp_by_T);
-    let results = compose_results(&records, params);
+    let Tc = locate_critical_temperature(&records);
+    let results = compose_results(&records, params, Tc);
 
     (results, file_name)
 }
@@ -171,18 +230,12 @@ fn main() {
     // make sure it's a valid directory
     assert!(Path::new(&dir_name).is_dir());
 
-    let Js = vec![0.2, 0.6, 1.0, 1.4, 1.8];
-
-    let hs = vec![0.4, 0.8, 1.2, 1.6, 2.0];
+    let export_frames = args().any(|arg| arg == "--frames");
 
-    let Js_and_hs = Js
-        .into_iter()
-        .map(|J| hs.clone().into_iter().map(move |h| (J, h)))
-        .flatten()
-        .collect::<Vec<_>>();
+    let Js = vec![0.2, 0.6, 1.0, 1.4, 1.8];
 
     let bar_count =
-        ((T_MAX - T_MIN) / T_STEP).floor() as u64 * MEASUREMENTS_PER_T as u64 * Js_and_hs.len() as u64;
+        ((T_MAX - T_MIN) / T_STEP).floor() as u64 * MEASUREMENTS_PER_T as u64 * Js.len() as u64;
     let (pb_tx, pb_rx) = channel();
 
     let handle = thread::spawn(move || {
@@ -199,15 +252,16 @@ fn main() {
         pb.finish_print("Finished!");
     });
 
-    let results = Js_and_hs
+    let results = Js
         .into_iter()
         .zip((0..).map(|_| pb_tx.clone()))
         .collect::<Vec<_>>()
         .into_par_iter()
-        .map(|((J, h), pb_tx)| {
-            let params = Params::new(J, h);
+        .map(|(J, pb_tx)| {
+            let seed = thread_rng().gen();
+            let params = Params::new(J, seed, dir_name.clone(), export_frames);
 
-            run(params, pb_tx)
+            run::<SmallRng>(params, pb_tx)
         })
         .collect::<Vec<(String, String)>>();
 
@@ -88,6 +88,78 @@ impl Iterator for TRange {
     }
 }
 
+/// Denominators smaller than this are treated as zero to avoid blowing up
+/// [`ConvergentSequence`]'s extrapolation.
+const EPSILON: f64 = 1e-12;
+
+/// Accelerates a stream of running estimates with Aitken's Δ² transform,
+/// which converges faster than the raw sequence whenever it converges
+/// geometrically.
+///
+/// Given three successive estimates `x_n`, `x_n+1`, `x_n+2`, the transform
+/// produces
+///
+/// ```text
+/// x'_n = x_n - (x_n+1 - x_n)^2 / (x_n+2 - 2*x_n+1 + x_n)
+/// ```
+///
+/// Feeding it one mean energy per block of flips during thermalization (as
+/// [`equilibrate`][crate::simulation::equilibrate] does) replaces a fixed
+/// flip count with an auto-detected equilibration point. The transform only
+/// extrapolates a sequence towards its limit; it does not locate the
+/// maximum of a sequence such as the susceptibility across a temperature
+/// sweep.
+pub struct ConvergentSequence {
+    samples: Vec<f64>,
+    tolerance: f64,
+    last_accelerated: Option<f64>,
+}
+
+impl ConvergentSequence {
+    /// Creates a new [`ConvergentSequence`] that reports convergence once
+    /// two successive accelerated estimates differ by less than
+    /// `tolerance`.
+    pub fn new(tolerance: f64) -> Self {
+        Self {
+            samples: Vec::new(),
+            tolerance,
+            last_accelerated: None,
+        }
+    }
+
+    /// Feeds the next raw estimate into the sequence, returning the
+    /// Aitken-accelerated estimate and whether it has converged.
+    ///
+    /// Returns `None` until at least three samples have been pushed. If the
+    /// transform's denominator is too close to zero, falls back to the
+    /// un-accelerated estimate instead of dividing by it.
+    pub fn push(&mut self, x: f64) -> Option<(f64, bool)> {
+        self.samples.push(x);
+
+        let n = self.samples.len();
+
+        if n < 3 {
+            return None;
+        }
+
+        let (x0, x1, x2) = (self.samples[n - 3], self.samples[n - 2], self.samples[n - 1]);
+        let denom = x2 - 2.0 * x1 + x0;
+
+        let accelerated = if denom.abs() < EPSILON {
+            x2
+        } else {
+            x2 - (x2 - x1).powi(2) / denom
+        };
+
+        let converged = self
+            .last_accelerated
+            .map_or(false, |prev| (accelerated - prev).abs() < self.tolerance);
+        self.last_accelerated = Some(accelerated);
+
+        Some((accelerated, converged))
+    }
+}
+
 #[cfg(test)]
 mod test {
     use ::pretty_assertions::assert_eq;
@@ -141,4 +213,59 @@ mod test {
 
         assert_eq!(T_range, vec![0.2, 0.3, 0.4, 0.5, 0.6, 0.7]);
     }
+
+    #[test]
+    fn test_convergent_sequence_needs_three_samples() {
+        let mut sequence = ConvergentSequence::new(0.01);
+
+        assert_eq!(sequence.push(1.0), None);
+        assert_eq!(sequence.push(1.5), None);
+        assert!(sequence.push(1.75).is_some());
+    }
+
+    #[test]
+    fn test_convergent_sequence_accelerates_geometric_series() {
+        // x_n = 2 - 0.5^n converges to 2 geometrically; Aitken's transform
+        // should land exactly on the limit from just three terms.
+        let mut sequence = ConvergentSequence::new(1e-6);
+        let xs: Vec<f64> = (0..5).map(|n| 2.0 - 0.5_f64.powi(n)).collect();
+
+        let mut last = None;
+
+        for x in xs {
+            last = sequence.push(x);
+        }
+
+        let (accelerated, _) = last.unwrap();
+
+        assert!(float_error(accelerated, 2.0) < 1e-6);
+    }
+
+    #[test]
+    fn test_convergent_sequence_reports_convergence() {
+        let mut sequence = ConvergentSequence::new(1e-6);
+        let xs: Vec<f64> = (0..6).map(|n| 2.0 - 0.5_f64.powi(n)).collect();
+
+        let mut converged_flags = Vec::new();
+
+        for x in xs {
+            if let Some((_, converged)) = sequence.push(x) {
+                converged_flags.push(converged);
+            }
+        }
+
+        assert_eq!(converged_flags.first(), Some(&false));
+        assert_eq!(converged_flags.last(), Some(&true));
+    }
+
+    #[test]
+    fn test_convergent_sequence_falls_back_on_tiny_denominator() {
+        let mut sequence = ConvergentSequence::new(0.01);
+        sequence.push(1.0);
+        sequence.push(1.0);
+
+        let (accelerated, _) = sequence.push(1.0).unwrap();
+
+        assert_eq!(accelerated, 1.0);
+    }
 }
@@ -3,6 +3,9 @@
 #![allow(non_snake_case)]
 use ::ndarray::prelude::*;
 use ::rand::prelude::*;
+use ::serde::{Deserialize, Serialize};
+
+use std::path::Path;
 
 /// Struct encapsulating the spin lattice and operations on it.
 ///
@@ -11,23 +14,47 @@ use ::rand::prelude::*;
 pub struct Lattice {
     size: usize,
     inner: Array2<i8>,
+    spin_sum: i64,
+    energy: f32,
+    best_E: Option<f32>,
+    best_inner: Option<Array2<i8>>,
+    /// Cluster-membership scratch space for [`wolff_step`][Lattice::wolff_step],
+    /// sized once and reused across calls instead of being reallocated on
+    /// every cluster grown.
+    cluster_buffer: Array2<bool>,
 }
 
 impl Lattice {
     /// Creates a new [`Lattice`] of a certain size with randomly generated
     /// spins.
-    pub fn new(size: usize) -> Self {
+    pub fn new(size: usize, J: f32) -> Self {
         let mut rng = thread_rng();
+
+        Self::new_seeded(size, J, &mut rng)
+    }
+
+    /// Creates a new [`Lattice`] of a certain size with randomly generated
+    /// spins, drawn from the given RNG.
+    ///
+    /// Unlike [`new`][Lattice::new], which always pulls from
+    /// [`thread_rng`][rand::thread_rng], this lets the caller supply a seeded
+    /// RNG (e.g. [`SmallRng`][rand::rngs::SmallRng] for throughput or a
+    /// ChaCha stream for cross-platform reproducibility) so a run can be
+    /// replayed exactly from its seed.
+    pub fn new_seeded<R: Rng>(size: usize, J: f32, rng: &mut R) -> Self {
         let spins: [i8; 2] = [-1, 1];
-        let inner = Array2::from_shape_fn((size, size), |_| {
-            *spins[..].choose(&mut rng).unwrap()
-        });
+        let inner =
+            Array2::from_shape_fn((size, size), |_| *spins[..].choose(rng).unwrap());
 
-        Self { size, inner }
+        Self::from_array(inner, J)
     }
 
     /// Creates a new [`Lattice`] from [`Array2<i8>`][ndarray::Array2].
     ///
+    /// `J` is used to compute the lattice's initial energy once, up front,
+    /// so [`current_E`][Lattice::current_E] is valid immediately instead of
+    /// requiring a throwaway flip to prime it.
+    ///
     /// # Examples
     ///
     /// ```
@@ -35,7 +62,7 @@ impl Lattice {
     /// # use ::ndarray::prelude::*;
     /// # use ising_lib::prelude::*;
     /// let array = Array::from_shape_vec((2, 2), vec![1, -1, 1, -1])?;
-    /// let lattice = Lattice::from_array(array);
+    /// let lattice = Lattice::from_array(array, 1.0);
     /// # Ok(())
     /// # }
     /// ```
@@ -52,7 +79,7 @@ impl Lattice {
     /// # use ising_lib::prelude::*;
     /// let array = Array::from_shape_vec((2, 2), vec![5, -1, 1, -1])?;
     /// //                                             ↑ incorrect spin value
-    /// let lattice = Lattice::from_array(array);
+    /// let lattice = Lattice::from_array(array, 1.0);
     /// # Ok(())
     /// # }
     /// ```
@@ -63,21 +90,32 @@ impl Lattice {
     /// # use ising_lib::prelude::*;
     /// let array = Array::from_shape_vec((1, 4), vec![1, 1, 1, 1])?;
     /// //                                 ↑  ↑ array isn't square
-    /// let lattice = Lattice::from_array(array);
+    /// let lattice = Lattice::from_array(array, 1.0);
     /// # Ok(())
     /// # }
     /// ```
-    pub fn from_array(array: Array2<i8>) -> Self {
+    pub fn from_array(array: Array2<i8>, J: f32) -> Self {
         assert!(array.is_square(), "Array is not square.");
         assert!(
             array.iter().all(|spin| *spin == 1 || *spin == -1),
             "Invalid spin value."
         );
 
-        Lattice {
-            size: array.shape()[0],
+        let spin_sum = array.iter().map(|&spin| i64::from(spin)).sum();
+        let size = array.shape()[0];
+
+        let mut lattice = Lattice {
+            size,
             inner: array,
-        }
+            spin_sum,
+            energy: 0.0,
+            best_E: None,
+            best_inner: None,
+            cluster_buffer: Array2::from_elem((size, size), false),
+        };
+        lattice.energy = lattice.calc_E(J);
+
+        lattice
     }
 
     /// Returns the size of the lattice.
@@ -143,7 +181,7 @@ impl Lattice {
     ///
     /// ```should_panic
     /// # use ising_lib::prelude::*;
-    /// let lattice = Lattice::new(10);
+    /// let lattice = Lattice::new(10, 1.0);
     /// let _ = lattice.calc_dE((42, 0), 1.0);
     /// ```
     pub fn calc_dE(&self, (i, j): (usize, usize), J: f32) -> f32 {
@@ -167,12 +205,176 @@ impl Lattice {
         f32::from(self.inner.sum().abs()) / self.size.pow(2) as f32
     }
 
-    /// Flips the `(ith, jth)` spin.
-    pub fn flip_spin(&mut self, (i, j): (usize, usize)) {
+    /// Flips the `(ith, jth)` spin, updating the cached energy and spin sum
+    /// by the local delta instead of re-summing the whole lattice.
+    ///
+    /// # Panics
+    ///
+    /// The function will panic if the index is out of bounds.
+    pub fn flip_spin(&mut self, (i, j): (usize, usize), J: f32) {
         assert!(i < self.size && j < self.size);
 
+        let old_spin = self.get((i, j));
+        let dE = self.calc_dE((i, j), J);
+        let E = self.energy + dE;
+
         *self.inner.get_mut((i, j)).unwrap() *= -1;
+
+        self.energy = E;
+        self.spin_sum -= 2 * i64::from(old_spin);
+
+        if E < self.best_E.unwrap_or(f32::INFINITY) {
+            self.best_E = Some(E);
+            self.best_inner = Some(self.inner.clone());
+        }
+    }
+
+    /// Returns the lattice's energy in `O(1)`. It is computed once at
+    /// construction and then updated incrementally by
+    /// [`flip_spin`][Lattice::flip_spin] and
+    /// [`wolff_step`][Lattice::wolff_step].
+    pub fn current_E(&self) -> f32 {
+        self.energy
+    }
+
+    /// Returns the lattice's magnetization, computed from the spin sum
+    /// maintained incrementally by [`flip_spin`][Lattice::flip_spin] and
+    /// [`wolff_step`][Lattice::wolff_step], in `O(1)`.
+    pub fn current_I(&self) -> f32 {
+        (self.spin_sum.abs() as f32) / self.size.pow(2) as f32
+    }
+
+    /// Returns the lowest-energy configuration seen by
+    /// [`flip_spin`][Lattice::flip_spin] or [`wolff_step`][Lattice::wolff_step]
+    /// so far, or `None` if neither has been called yet.
+    pub fn best_snapshot(&self) -> Option<&Array2<i8>> {
+        self.best_inner.as_ref()
+    }
+
+    /// Performs a single Wolff cluster update, growing an aligned cluster of
+    /// spins from a random seed site and flipping it all at once.
+    ///
+    /// Starting at a random site, the cluster grows by visiting each member's
+    /// four torus neighbors and adding any neighbor that shares its spin with
+    /// probability `P_add = 1 - exp(-2 * J / (K * T))`. This whole-cluster
+    /// flip decorrelates the lattice far faster than single-spin Metropolis
+    /// updates near the critical temperature, where Metropolis suffers from
+    /// critical slowing down.
+    ///
+    /// Cluster membership is tracked in `self.cluster_buffer`, reused across
+    /// calls instead of allocated fresh each time. The cluster's spins are
+    /// flipped directly (not through [`flip_spin`][Lattice::flip_spin]) and
+    /// the best-seen snapshot is checked once against the final post-cluster
+    /// energy, so the half-flipped intermediate configurations visited while
+    /// flipping the cluster - which the walk never actually rests in - can't
+    /// be recorded as the "best" one.
+    ///
+    /// Returns the number of spins the cluster contained (and flipped), so
+    /// callers can relate "flips per measurement" to an equivalent number of
+    /// cluster moves.
+    pub fn wolff_step<R: Rng>(&mut self, J: f32, T: f32, K: f32, rng: &mut R) -> usize {
+        let seed = (rng.gen_range(0, self.size), rng.gen_range(0, self.size));
+        let seed_spin = self.get(seed);
+        let p_add = 1.0 - (-2.0 * J / (K * T)).exp();
+
+        self.cluster_buffer.fill(false);
+        let mut cluster = vec![seed];
+        let mut stack = vec![seed];
+        self.cluster_buffer[seed] = true;
+
+        while let Some((i, j)) = stack.pop() {
+            let neighbors = [
+                (self.roll_index(i, 1), j),
+                (self.roll_index(i, -1), j),
+                (i, self.roll_index(j, 1)),
+                (i, self.roll_index(j, -1)),
+            ];
+
+            for &n in &neighbors {
+                if !self.cluster_buffer[n] && self.get(n) == seed_spin && rng.gen::<f32>() < p_add
+                {
+                    self.cluster_buffer[n] = true;
+                    cluster.push(n);
+                    stack.push(n);
+                }
+            }
+        }
+
+        for &ix in &cluster {
+            let old_spin = self.get(ix);
+            let dE = self.calc_dE(ix, J);
+
+            *self.inner.get_mut(ix).unwrap() *= -1;
+
+            self.energy += dE;
+            self.spin_sum -= 2 * i64::from(old_spin);
+        }
+
+        if self.energy < self.best_E.unwrap_or(f32::INFINITY) {
+            self.best_E = Some(self.energy);
+            self.best_inner = Some(self.inner.clone());
+        }
+
+        cluster.len()
     }
+
+    /// Returns the raw spin grid, e.g. to export a frame of the lattice's
+    /// current configuration.
+    pub fn as_array(&self) -> &Array2<i8> {
+        &self.inner
+    }
+
+    /// Saves the spin configuration to `path` as JSON, so a long run can be
+    /// checkpointed and resumed later with [`load`][Lattice::load] instead of
+    /// starting from a fresh random configuration.
+    ///
+    /// Only the spin grid and its size are saved - the incremental
+    /// energy/spin-sum bookkeeping is re-derived from the grid on
+    /// [`load`][Lattice::load], and the best-seen snapshot starts out empty.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error>> {
+        let snapshot = LatticeSnapshot {
+            size: self.size,
+            inner: self.inner.clone(),
+        };
+        let json = ::serde_json::to_string_pretty(&snapshot)?;
+
+        std::fs::write(path, json)?;
+
+        Ok(())
+    }
+
+    /// Loads a [`Lattice`] previously written by [`save`][Lattice::save].
+    ///
+    /// Unlike [`from_array`][Lattice::from_array], a malformed or hand-edited
+    /// save file is surfaced as an `Err` instead of panicking - `J` is only
+    /// used to re-derive the energy cache once the grid has been validated.
+    pub fn load(path: impl AsRef<Path>, J: f32) -> Result<Self, Box<dyn std::error::Error>> {
+        let json = std::fs::read_to_string(path)?;
+        let snapshot: LatticeSnapshot = ::serde_json::from_str(&json)?;
+
+        if !snapshot.inner.is_square() {
+            return Err("saved lattice is not square".into());
+        }
+
+        if !snapshot
+            .inner
+            .iter()
+            .all(|&spin| spin == 1 || spin == -1)
+        {
+            return Err("saved lattice contains an invalid spin value".into());
+        }
+
+        Ok(Self::from_array(snapshot.inner, J))
+    }
+}
+
+/// On-disk representation of a [`Lattice`] used by
+/// [`save`][Lattice::save]/[`load`][Lattice::load] - the spin grid and its
+/// `size`; the incremental bookkeeping is re-derived from the grid.
+#[derive(Serialize, Deserialize)]
+struct LatticeSnapshot {
+    size: usize,
+    inner: Array2<i8>,
 }
 
 #[cfg(test)]
@@ -184,18 +386,30 @@ mod test {
     #[test]
     fn test_create_lattice() {
         let t_size = 40;
-        let lattice = Lattice::new(t_size);
+        let lattice = Lattice::new(t_size, 1.0);
 
         assert_eq!(lattice.size(), t_size);
     }
 
+    #[test]
+    fn test_new_seeded_is_reproducible() {
+        let t_size = 10;
+        let mut rng_a = SmallRng::seed_from_u64(42);
+        let mut rng_b = SmallRng::seed_from_u64(42);
+
+        let lattice_a = Lattice::new_seeded(t_size, 1.0, &mut rng_a);
+        let lattice_b = Lattice::new_seeded(t_size, 1.0, &mut rng_b);
+
+        assert_eq!(lattice_a.inner, lattice_b.inner);
+    }
+
     #[test]
     fn test_create_lattice_from_array() {
         let t_size = 2;
         let t_array =
             Array::from_shape_vec((t_size, t_size), vec![1, -1, 1, -1])
                 .unwrap();
-        let lattice = Lattice::from_array(t_array);
+        let lattice = Lattice::from_array(t_array, 1.0);
 
         assert_eq!(lattice.size(), t_size);
     }
@@ -204,7 +418,7 @@ mod test {
     fn test_spin_times_neighbors() {
         let spins = [-1, -1, 1, 1, 1, 1, 1, 1, -1];
         let t_array = Array::from_shape_vec((3, 3), spins.to_vec()).unwrap();
-        let lattice = Lattice::from_array(t_array);
+        let lattice = Lattice::from_array(t_array, 1.0);
 
         let product = lattice.spin_times_all_neighbors((1, 1));
         let t_product = (-1 + 1 + 1 + 1) * 1;
@@ -217,8 +431,8 @@ mod test {
         let t_array =
             Array::from_shape_vec((3, 3), vec![-1, -1, 1, 1, 1, 1, -1, 1, 1])
                 .unwrap();
-        let lattice = Lattice::from_array(t_array);
         let J = 1.0;
+        let lattice = Lattice::from_array(t_array, J);
         let dE = lattice.calc_dE((1, 1), J);
         let t_dE =
             2.0 * J * f32::from(lattice.spin_times_all_neighbors((1, 1)));
@@ -230,8 +444,8 @@ mod test {
     fn test_caluclate_E() {
         let t_array =
             Array::from_shape_vec((2, 2), vec![-1, -1, 1, 1]).unwrap();
-        let lattice = Lattice::from_array(t_array);
         let J = 1.0;
+        let lattice = Lattice::from_array(t_array, J);
 
         let E = lattice.calc_E(J);
         let t_E = 0.0;
@@ -243,7 +457,7 @@ mod test {
     fn test_calculate_I() {
         let t_array =
             Array::from_shape_vec((2, 2), vec![-1, -1, -1, 1]).unwrap();
-        let lattice = Lattice::from_array(t_array);
+        let lattice = Lattice::from_array(t_array, 1.0);
 
         let I = lattice.calc_I();
         let t_I = (-1_i8 + -1 + -1 + 1).abs() as f32 / 4.0;
@@ -255,11 +469,165 @@ mod test {
     fn test_flip_spin() {
         let t_array =
             Array::from_shape_vec((2, 2), vec![-1, -1, -1, 1]).unwrap();
-        let mut lattice = Lattice::from_array(t_array);
+        let mut lattice = Lattice::from_array(t_array, 1.0);
 
-        lattice.flip_spin((1, 1));
+        lattice.flip_spin((1, 1), 1.0);
         let spin = lattice.get((1, 1));
 
         assert_eq!(spin, -1);
     }
+
+    #[test]
+    fn test_current_E_matches_full_recomputation() {
+        let t_array =
+            Array::from_shape_vec((3, 3), vec![-1, -1, 1, 1, 1, 1, -1, 1, 1])
+                .unwrap();
+        let J = 1.0;
+        let mut lattice = Lattice::from_array(t_array, J);
+
+        lattice.flip_spin((1, 1), J);
+        lattice.flip_spin((0, 2), J);
+
+        assert_eq!(lattice.current_E(), lattice.calc_E(J));
+    }
+
+    #[test]
+    fn test_current_I_matches_calc_I() {
+        let t_array =
+            Array::from_shape_vec((2, 2), vec![-1, -1, -1, 1]).unwrap();
+        let mut lattice = Lattice::from_array(t_array, 1.0);
+
+        lattice.flip_spin((0, 0), 1.0);
+
+        assert_eq!(lattice.current_I(), lattice.calc_I());
+    }
+
+    #[test]
+    fn test_best_snapshot_tracks_minimum_energy() {
+        let t_array = Array::from_elem((4, 4), 1_i8);
+        let J = 1.0;
+        let mut lattice = Lattice::from_array(t_array, J);
+
+        assert!(lattice.best_snapshot().is_none());
+
+        // Flipping a spin out of the uniform ground state raises the energy.
+        lattice.flip_spin((0, 0), J);
+        assert!(lattice.best_snapshot().is_some());
+
+        // Flipping it back restores the ground state, which becomes the new
+        // (lower-energy) best snapshot.
+        lattice.flip_spin((0, 0), J);
+
+        let best_E = lattice
+            .best_snapshot()
+            .map(|snapshot| Lattice::from_array(snapshot.clone(), J).calc_E(J))
+            .unwrap();
+
+        assert_eq!(best_E, lattice.calc_E(J));
+        assert_eq!(lattice.best_snapshot().unwrap(), &Array2::from_elem((4, 4), 1_i8));
+    }
+
+    #[test]
+    fn test_wolff_step_cluster_size_is_bounded() {
+        let t_size = 10;
+        let mut lattice = Lattice::new(t_size, 1.0);
+        let mut rng = SmallRng::seed_from_u64(0);
+
+        let cluster_size = lattice.wolff_step(1.0, 2.0, 1.0, &mut rng);
+
+        assert!(cluster_size >= 1 && cluster_size <= t_size * t_size);
+    }
+
+    #[test]
+    fn test_wolff_step_flips_uniform_lattice_whole() {
+        // At a very low T and high J, P_add is virtually 1, so a uniform
+        // lattice's cluster is guaranteed to grow to the whole torus and the
+        // magnetization magnitude is preserved by the all-at-once flip.
+        let t_array = Array::from_elem((4, 4), 1_i8);
+        let mut lattice = Lattice::from_array(t_array, 1.0);
+        let mut rng = SmallRng::seed_from_u64(0);
+
+        let cluster_size = lattice.wolff_step(10.0, 0.001, 1.0, &mut rng);
+
+        assert_eq!(cluster_size, 16);
+        assert_eq!(lattice.calc_I(), 1.0);
+    }
+
+    #[test]
+    fn test_wolff_step_current_E_matches_full_recomputation() {
+        let t_array = Array::from_shape_vec(
+            (4, 4),
+            vec![
+                -1, -1, 1, 1, 1, 1, -1, -1, -1, 1, 1, -1, 1, -1, -1, 1,
+            ],
+        )
+        .unwrap();
+        let J = 1.0;
+        let mut lattice = Lattice::from_array(t_array, J);
+        let mut rng = SmallRng::seed_from_u64(0);
+
+        lattice.wolff_step(J, 2.0, 1.0, &mut rng);
+
+        assert_eq!(lattice.current_E(), lattice.calc_E(J));
+    }
+
+    #[test]
+    fn test_wolff_step_best_snapshot_reflects_only_final_cluster_state() {
+        // Flipping one spin out and back with single-spin flips records the
+        // uniform ground state as the best_snapshot baseline.
+        let t_array = Array::from_elem((4, 4), 1_i8);
+        let J = 1.0;
+        let mut lattice = Lattice::from_array(t_array, J);
+        lattice.flip_spin((0, 0), J);
+        lattice.flip_spin((0, 0), J);
+        let best_E_before = lattice.best_snapshot().map(|s| Lattice::from_array(s.clone(), J).calc_E(J));
+
+        // At very low T and high J, the whole lattice flips as one cluster;
+        // since it's the uniform ground state, the final energy is
+        // unchanged, but every half-flipped intermediate configuration
+        // visited while flipping it has strictly higher energy. None of
+        // those intermediates should ever become the recorded best.
+        let mut rng = SmallRng::seed_from_u64(0);
+        lattice.wolff_step(10.0, 0.001, 1.0, &mut rng);
+
+        let best_E_after = lattice.best_snapshot().map(|s| Lattice::from_array(s.clone(), J).calc_E(J));
+
+        assert_eq!(best_E_after, best_E_before);
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let t_array =
+            Array::from_shape_vec((3, 3), vec![1, -1, 1, -1, 1, -1, 1, -1, 1])
+                .unwrap();
+        let lattice = Lattice::from_array(t_array, 1.0);
+        let path = std::env::temp_dir()
+            .join("ising_test_save_and_load_round_trip.json");
+
+        lattice.save(&path).unwrap();
+        let loaded = Lattice::load(&path, 1.0).unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.size(), lattice.size());
+        assert_eq!(loaded.inner, lattice.inner);
+    }
+
+    #[test]
+    fn test_load_rejects_invalid_spin_values_instead_of_panicking() {
+        let snapshot = LatticeSnapshot {
+            size: 2,
+            inner: Array::from_shape_vec((2, 2), vec![1, -1, 5, -1]).unwrap(),
+        };
+        let path = std::env::temp_dir()
+            .join("ising_test_load_rejects_invalid_spin_values.json");
+        let json = ::serde_json::to_string_pretty(&snapshot).unwrap();
+        std::fs::write(&path, json).unwrap();
+
+        let result = Lattice::load(&path, 1.0);
+
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+    }
 }
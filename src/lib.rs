@@ -9,3 +9,4 @@
 pub mod calculations;
 pub mod lattice;
 pub mod prelude;
+pub mod simulation;
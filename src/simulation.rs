@@ -1,7 +1,134 @@
 //! Utitilities for simulation.
 
+#![allow(non_snake_case)]
+use ::rand::prelude::*;
+
+use crate::{
+    calculations::{calc_flip_probability, ConvergentSequence},
+    lattice::Lattice,
+};
+
+/// Anneals `lattice` by cycles of `flips_per_cycle` attempted Metropolis
+/// flips, adapting the temperature after every cycle instead of following a
+/// fixed schedule.
+///
+/// After each cycle, the fraction of accepted flips (the acceptance rate) is
+/// passed to `control`, which returns the temperature for the next cycle or
+/// `None` to stop annealing - e.g. "keep lowering `T` while the acceptance
+/// rate stays above a threshold". Returns the `(T, E)` history of every
+/// cycle, where `E` is read from
+/// [`Lattice::current_E`][crate::lattice::Lattice::current_E] at no extra
+/// cost beyond the flips already performed.
+pub fn anneal<R: Rng>(
+    lattice: &mut Lattice,
+    J: f32,
+    K: f32,
+    T_init: f64,
+    flips_per_cycle: usize,
+    rng: &mut R,
+    mut control: impl FnMut(f64) -> Option<f64>,
+) -> Vec<(f64, f32)> {
+    let mut T = T_init;
+    let mut history = Vec::new();
+
+    loop {
+        let mut accepted = 0;
+
+        for _ in 0..flips_per_cycle {
+            let ix = (
+                rng.gen_range(0, lattice.size()),
+                rng.gen_range(0, lattice.size()),
+            );
+            let E_diff = f64::from(lattice.calc_dE(ix, J));
+            let probability = calc_flip_probability(E_diff, T, f64::from(K));
+
+            if probability > rng.gen() {
+                lattice.flip_spin(ix, J);
+                accepted += 1;
+            }
+        }
+
+        let success_rate = if flips_per_cycle == 0 {
+            0.0
+        } else {
+            accepted as f64 / flips_per_cycle as f64
+        };
+        history.push((T, lattice.current_E()));
+
+        match control(success_rate) {
+            Some(next_T) => T = next_T,
+            None => break,
+        }
+    }
+
+    history
+}
+
+/// "Cools" `lattice` towards its natural state at temperature `T`, attempting
+/// Metropolis flips in blocks of `block_size` and stopping early once the
+/// block-mean energy stops moving by more than `tolerance`, instead of always
+/// spending the full `max_flips` budget.
+///
+/// Unlike feeding [`ConvergentSequence`] the cumulative running-mean energy -
+/// whose step-to-step delta shrinks like `1/step` purely from averaging more
+/// samples, regardless of whether the chain has actually settled - this feeds
+/// it one mean per block, so convergence reflects the block means
+/// themselves flattening out rather than an ever-growing sample count
+/// damping the signal.
+///
+/// Returns the number of flips attempted before stopping.
+pub fn equilibrate<R: Rng>(
+    lattice: &mut Lattice,
+    J: f32,
+    K: f32,
+    T: f64,
+    block_size: usize,
+    max_flips: usize,
+    tolerance: f64,
+    rng: &mut R,
+) -> usize {
+    let mut convergence = ConvergentSequence::new(tolerance);
+    let mut flips_attempted = 0;
+
+    while flips_attempted < max_flips {
+        let block_len = if block_size == 0 {
+            1
+        } else {
+            block_size.min(max_flips - flips_attempted)
+        };
+        let mut block_E_sum = 0.0_f64;
+
+        for _ in 0..block_len {
+            let ix = (
+                rng.gen_range(0, lattice.size()),
+                rng.gen_range(0, lattice.size()),
+            );
+            let E_diff = f64::from(lattice.calc_dE(ix, J));
+            let probability = calc_flip_probability(E_diff, T, f64::from(K));
+
+            if probability > rng.gen() {
+                lattice.flip_spin(ix, J);
+            }
+
+            block_E_sum += f64::from(lattice.current_E());
+        }
+
+        flips_attempted += block_len;
+        let block_mean_E = block_E_sum / block_len as f64;
+
+        if let Some((_, converged)) = convergence.push(block_mean_E) {
+            if converged {
+                break;
+            }
+        }
+    }
+
+    flips_attempted
+}
+
 #[cfg(test)]
 mod test {
+    use ::ndarray::prelude::*;
     use ::pretty_assertions::assert_eq;
 
     use super::*;
@@ -24,4 +151,57 @@ mod test {
 
         assert_eq!(state, 10);
     }
+
+    #[test]
+    fn test_anneal_stops_when_control_returns_none() {
+        let mut lattice = Lattice::new(4, 1.0);
+        let mut rng = SmallRng::seed_from_u64(0);
+        let mut cycles_left = 3;
+
+        let history = anneal(
+            &mut lattice,
+            1.0,
+            1.0,
+            2.0,
+            10,
+            &mut rng,
+            |_success_rate| {
+                cycles_left -= 1;
+
+                if cycles_left > 0 {
+                    Some(1.0)
+                } else {
+                    None
+                }
+            },
+        );
+
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.last().unwrap().1, lattice.current_E());
+    }
+
+    #[test]
+    fn test_equilibrate_stops_before_max_flips_once_converged() {
+        let t_array = Array::from_elem((4, 4), 1_i8);
+        let mut lattice = Lattice::from_array(t_array, 1.0);
+        let mut rng = SmallRng::seed_from_u64(0);
+
+        let flips_attempted =
+            equilibrate(&mut lattice, 1.0, 1.0, 0.01, 10, 10_000, 1e-6, &mut rng);
+
+        assert!(flips_attempted < 10_000);
+    }
+
+    #[test]
+    fn test_equilibrate_with_zero_block_size_still_terminates() {
+        // block_size == 0 used to leave block_len at 0 forever, so
+        // flips_attempted never advanced and the loop never returned.
+        let t_array = Array::from_elem((4, 4), 1_i8);
+        let mut lattice = Lattice::from_array(t_array, 1.0);
+        let mut rng = SmallRng::seed_from_u64(0);
+
+        let flips_attempted = equilibrate(&mut lattice, 1.0, 1.0, 0.01, 0, 100, 1e-6, &mut rng);
+
+        assert!(flips_attempted <= 100);
+    }
 }